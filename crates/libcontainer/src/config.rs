@@ -0,0 +1,89 @@
+use std::{
+    fmt::{self, Display},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use oci_spec::runtime::{Hooks, Spec};
+use serde::{Deserialize, Serialize};
+
+use crate::utils;
+
+const YOUKI_CONFIG_NAME: &str = "youki_config.json";
+
+/// Selects which driver youki uses to manage the container's cgroups.
+///
+/// The resolved variant is persisted in [`YoukiConfig`] so later lifecycle
+/// commands — and the `state`/`container_events` output — report the driver
+/// actually in effect rather than inferring it from a systemd flag.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CgroupManager {
+    /// Delegate cgroup management to systemd via its transient-unit API.
+    Systemd,
+    /// Manage the cgroup hierarchy directly through the cgroupfs.
+    CgroupFs,
+    /// Probe the host (cgroup v1 vs v2, presence of a systemd session bus)
+    /// and pick the most appropriate driver, falling back to cgroupfs in
+    /// rootless-without-systemd environments.
+    Auto,
+}
+
+impl Display for CgroupManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CgroupManager::Systemd => "systemd",
+            CgroupManager::CgroupFs => "cgroupfs",
+            CgroupManager::Auto => "auto",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A configuration for passing information obtained during container creation
+/// to other commands. Saved inside the container directory so that subsequent
+/// operations (e.g. `delete`, `state`) act on the same settings the container
+/// was created with.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct YoukiConfig {
+    pub hooks: Option<Hooks>,
+    pub cgroup_path: PathBuf,
+    pub cgroup_manager: CgroupManager,
+}
+
+impl YoukiConfig {
+    pub fn from_spec(
+        spec: &Spec,
+        container_id: &str,
+        rootless: bool,
+        cgroup_manager: CgroupManager,
+    ) -> Result<Self> {
+        Ok(YoukiConfig {
+            hooks: spec.hooks().clone(),
+            cgroup_path: utils::get_cgroup_path(
+                spec.linux()
+                    .as_ref()
+                    .context("no linux in spec")?
+                    .cgroups_path(),
+                container_id,
+                rootless,
+            ),
+            cgroup_manager,
+        })
+    }
+
+    pub fn save(&self, container_dir: &Path) -> Result<()> {
+        let file = container_dir.join(YOUKI_CONFIG_NAME);
+        fs::write(file, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(container_dir: &Path) -> Result<Self> {
+        let file = container_dir.join(YOUKI_CONFIG_NAME);
+        let config = serde_json::from_reader(io::BufReader::new(
+            fs::File::open(&file)
+                .with_context(|| format!("failed to open {}", file.display()))?,
+        ))?;
+        Ok(config)
+    }
+}
@@ -4,11 +4,15 @@ use oci_spec::runtime::Spec;
 use rootless::Rootless;
 use std::{
     fs,
+    os::unix::fs::PermissionsExt,
     path::{Path, PathBuf},
 };
 
 use crate::{
-    apparmor, config::YoukiConfig, notify_socket::NOTIFY_FILE, process::args::ContainerType,
+    apparmor,
+    config::{CgroupManager, YoukiConfig},
+    notify_socket::NOTIFY_FILE,
+    process::args::ContainerType,
     rootless, tty, utils,
 };
 
@@ -16,11 +20,88 @@ use super::{
     builder::ContainerBuilder, builder_impl::ContainerBuilderImpl, Container, ContainerStatus,
 };
 
+// Highest OCI runtime-spec version youki implements in full. Bundles on the
+// same major line but a newer minor are accepted with a per-feature warning.
+const SUPPORTED_SPEC_MAJOR: u32 = 1;
+const SUPPORTED_SPEC_MINOR: u32 = 0;
+
+// Features introduced in a given runtime-spec minor version that youki does not
+// yet fully honor, used to warn callers about exactly what is unsupported.
+const UNIMPLEMENTED_SPEC_FEATURES: &[(u32, &str)] = &[
+    (1, "seccomp notify, rlimits additions and the time namespace (1.1.x)"),
+];
+
+/// The cgroup hierarchy layout detected on the host.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CgroupVersion {
+    V1,
+    V2,
+}
+
+impl CgroupManager {
+    /// Resolves `Auto` to a concrete driver for the current host, factoring in
+    /// both whether a systemd session is reachable and whether the host runs a
+    /// cgroup v1 or v2 hierarchy. The `Systemd`/`CgroupFs` variants resolve to
+    /// themselves.
+    fn resolve(self, rootless: bool) -> CgroupManager {
+        match self {
+            CgroupManager::Systemd => CgroupManager::Systemd,
+            CgroupManager::CgroupFs => CgroupManager::CgroupFs,
+            CgroupManager::Auto => {
+                let version = Self::cgroup_version();
+                match (Self::systemd_session_available(rootless), version) {
+                    // systemd is reachable: let it drive, it manages both v1 and v2.
+                    (true, _) => CgroupManager::Systemd,
+                    // No systemd on a unified v2 hierarchy: cgroupfs works
+                    // directly, including rootless with controller delegation.
+                    (false, CgroupVersion::V2) => CgroupManager::CgroupFs,
+                    // No systemd on legacy v1: direct management is best-effort
+                    // (rootless v1 has no delegation); fall back to cgroupfs.
+                    (false, CgroupVersion::V1) => {
+                        log::warn!(
+                            "no systemd session available on a cgroup v1 host; \
+                            falling back to cgroupfs, which may be limited when rootless"
+                        );
+                        CgroupManager::CgroupFs
+                    }
+                }
+            }
+        }
+    }
+
+    /// Detects whether the host exposes a cgroup v2 unified hierarchy or the
+    /// legacy v1 layout. The unified hierarchy mounts a `cgroup.controllers`
+    /// file at the root of the cgroup mount point.
+    fn cgroup_version() -> CgroupVersion {
+        if Path::new("/sys/fs/cgroup/cgroup.controllers").exists() {
+            CgroupVersion::V2
+        } else {
+            CgroupVersion::V1
+        }
+    }
+
+    /// Whether a systemd session bus is reachable to drive cgroup management.
+    /// A rootless container needs the user session bus; otherwise the system
+    /// bus, indicated by the running systemd manager directory, is required.
+    fn systemd_session_available(rootless: bool) -> bool {
+        if rootless {
+            std::env::var_os("DBUS_SESSION_BUS_ADDRESS").is_some()
+        } else {
+            Path::new("/run/systemd/system").exists()
+        }
+    }
+
+    fn uses_systemd(self) -> bool {
+        matches!(self, CgroupManager::Systemd)
+    }
+}
+
 // Builder that can be used to configure the properties of a new container
 pub struct InitContainerBuilder<'a> {
     base: ContainerBuilder<'a>,
     bundle: PathBuf,
-    use_systemd: bool,
+    cgroup_manager: CgroupManager,
+    detached: bool,
 }
 
 impl<'a> InitContainerBuilder<'a> {
@@ -30,13 +111,38 @@ impl<'a> InitContainerBuilder<'a> {
         Self {
             base: builder,
             bundle,
-            use_systemd: true,
+            cgroup_manager: CgroupManager::Systemd,
+            detached: false,
         }
     }
 
-    /// Sets if systemd should be used for managing cgroups
-    pub fn with_systemd(mut self, should_use: bool) -> Self {
-        self.use_systemd = should_use;
+    /// Sets if systemd should be used for managing cgroups.
+    ///
+    /// Retained as a convenience over [`with_cgroup_manager`]: `true` selects
+    /// [`CgroupManager::Systemd`] and `false` selects [`CgroupManager::CgroupFs`].
+    ///
+    /// [`with_cgroup_manager`]: Self::with_cgroup_manager
+    pub fn with_systemd(self, should_use: bool) -> Self {
+        let manager = if should_use {
+            CgroupManager::Systemd
+        } else {
+            CgroupManager::CgroupFs
+        };
+        self.with_cgroup_manager(manager)
+    }
+
+    /// Selects the cgroup driver youki uses for this container. Use
+    /// [`CgroupManager::Auto`] to let youki probe the host and decide.
+    pub fn with_cgroup_manager(mut self, manager: CgroupManager) -> Self {
+        self.cgroup_manager = manager;
+        self
+    }
+
+    /// Sets whether the container runs detached (as in `create`) or in the
+    /// foreground (as in `run`). When not detached, `build` keeps the console
+    /// and notify socket wiring able to block on the foreground process.
+    pub fn with_detached(mut self, detached: bool) -> Self {
+        self.detached = detached;
         self
     }
 
@@ -47,11 +153,19 @@ impl<'a> InitContainerBuilder<'a> {
             .create_container_dir()
             .context("failed to create container dir")?;
 
+        let rootless = Rootless::new(&spec)?;
+        // Resolve the requested driver (handling `Auto`) and record the
+        // decision on the container state so `state`/`container_events` report
+        // which cgroup manager is actually in effect.
+        let cgroup_manager = self.cgroup_manager.resolve(rootless.is_some());
+        let use_systemd = cgroup_manager.uses_systemd();
+        log::debug!("using {:?} cgroup manager", cgroup_manager);
+
         let mut container = self
             .create_container_state(&container_dir)
             .context("failed to create container state")?;
         container
-            .set_systemd(self.use_systemd)
+            .set_systemd(use_systemd)
             .set_annotations(spec.annotations().clone());
 
         unistd::chdir(&container_dir)?;
@@ -71,8 +185,10 @@ impl<'a> InitContainerBuilder<'a> {
             None
         };
 
-        let rootless = Rootless::new(&spec)?;
-        let config = YoukiConfig::from_spec(&spec, container.id(), rootless.is_some())?;
+        // Persist the concrete resolved driver (`Auto` is never stored) so the
+        // saved config records which cgroup manager is actually in effect.
+        let config =
+            YoukiConfig::from_spec(&spec, container.id(), rootless.is_some(), cgroup_manager)?;
         config
             .save(&container_dir)
             .context("failed to save config")?;
@@ -83,14 +199,14 @@ impl<'a> InitContainerBuilder<'a> {
             container_id: self.base.container_id,
             pid_file: self.base.pid_file,
             console_socket: csocketfd,
-            use_systemd: self.use_systemd,
+            use_systemd,
             spec: &spec,
             rootfs,
             rootless,
             notify_path,
             container: Some(container.clone()),
             preserve_fds: self.base.preserve_fds,
-            detached: false, // TODO this should be set properly based on how the command is given
+            detached: self.detached,
         };
 
         builder_impl.create()?;
@@ -123,12 +239,8 @@ impl<'a> InitContainerBuilder<'a> {
     }
 
     fn validate_spec(spec: &Spec) -> Result<()> {
-        if !spec.version().starts_with("1.0") {
-            bail!(
-                "runtime spec has incompatible version '{}'. Only 1.0.X is supported",
-                spec.version()
-            );
-        }
+        Self::validate_spec_version(spec.version())
+            .context("failed to validate runtime spec version")?;
 
         if let Some(process) = spec.process() {
             if let Some(profile) = process.apparmor_profile() {
@@ -142,9 +254,169 @@ impl<'a> InitContainerBuilder<'a> {
             }
         }
 
+        Self::validate_hooks(spec).context("failed to validate hooks")?;
+
+        Ok(())
+    }
+
+    /// Validates the `hooks` section of the spec before any namespaces or
+    /// cgroups are created, so an invalid hook is reported up front rather than
+    /// blowing up mid-lifecycle on a half-created container.
+    ///
+    /// Every hook has its `timeout` checked. The host-path existence/executable
+    /// check is applied only to the host-side stages (`prestart`,
+    /// `createRuntime`, `poststart`, `poststop`): `createContainer` and
+    /// `startContainer` run inside the container namespace, so their binary
+    /// lives in the container rootfs and legitimately may not exist on the host
+    /// at `build()` time — those two stages are intentionally validated for
+    /// `timeout` only.
+    fn validate_hooks(spec: &Spec) -> Result<()> {
+        let hooks = match spec.hooks() {
+            Some(hooks) => hooks,
+            None => return Ok(()),
+        };
+
+        let prestart = hooks.prestart().as_ref();
+        let has_lifecycle = hooks.create_runtime().is_some()
+            || hooks.create_container().is_some()
+            || hooks.start_container().is_some();
+        if prestart.is_some() && has_lifecycle {
+            log::warn!(
+                "spec uses the deprecated 'prestart' hooks alongside the newer \
+                createRuntime/createContainer/startContainer hooks; prefer the \
+                lifecycle hooks as 'prestart' is deprecated"
+            );
+        }
+
+        // `createContainer`/`startContainer` hooks execute inside the container
+        // namespace, so their binary lives in the container rootfs and need not
+        // exist on the host yet; only the host-side stages get a host-path check.
+        let stages = [
+            ("prestart", hooks.prestart(), true),
+            ("createRuntime", hooks.create_runtime(), true),
+            ("createContainer", hooks.create_container(), false),
+            ("startContainer", hooks.start_container(), false),
+            ("poststart", hooks.poststart(), true),
+            ("poststop", hooks.poststop(), true),
+        ];
+
+        for (stage, entries, host_side) in stages {
+            if let Some(entries) = entries {
+                for hook in entries {
+                    Self::validate_hook(stage, hook, host_side)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_hook(stage: &str, hook: &oci_spec::runtime::Hook, host_side: bool) -> Result<()> {
+        let path = hook.path();
+        if host_side {
+            let metadata = fs::metadata(path).with_context(|| {
+                format!(
+                    "{} hook path '{}' does not exist or is not accessible",
+                    stage,
+                    path.display()
+                )
+            })?;
+
+            if metadata.permissions().mode() & 0o111 == 0 {
+                bail!(
+                    "{} hook path '{}' is not executable",
+                    stage,
+                    path.display()
+                );
+            }
+        }
+
+        if let Some(timeout) = hook.timeout() {
+            if timeout <= 0 {
+                bail!(
+                    "{} hook '{}' has a non-positive timeout of {}",
+                    stage,
+                    path.display(),
+                    timeout
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the runtime spec advertises an OCI version youki can honor.
+    ///
+    /// youki tracks the `1.x` line of the runtime-spec. Any `1.y` bundle is
+    /// accepted, but minor versions that introduce features youki has not yet
+    /// implemented are accepted with a warning listing exactly what is missing,
+    /// so callers are never silently misled about coverage.
+    fn validate_spec_version(version: &str) -> Result<()> {
+        let (major, minor, _patch) = Self::parse_version(version)
+            .with_context(|| format!("malformed runtime spec version '{}'", version))?;
+
+        if major != SUPPORTED_SPEC_MAJOR {
+            bail!(
+                "runtime spec has incompatible version '{}'. \
+                Only the {}.x line (up to {}.{}) is supported",
+                version,
+                SUPPORTED_SPEC_MAJOR,
+                SUPPORTED_SPEC_MAJOR,
+                SUPPORTED_SPEC_MINOR,
+            );
+        }
+
+        // Minor versions beyond the one we fully implement carry features youki
+        // may not honor yet. Warn per known gap rather than proceeding silently.
+        if minor > SUPPORTED_SPEC_MINOR {
+            for (feature_minor, feature) in UNIMPLEMENTED_SPEC_FEATURES {
+                if minor >= *feature_minor {
+                    log::warn!(
+                        "runtime spec version '{}' requests '{}', which youki does not \
+                        yet fully support (supported range {}.0 - {}.{})",
+                        version,
+                        feature,
+                        SUPPORTED_SPEC_MAJOR,
+                        SUPPORTED_SPEC_MAJOR,
+                        SUPPORTED_SPEC_MINOR,
+                    );
+                }
+            }
+        }
+
         Ok(())
     }
 
+    /// Parses a `major.minor.patch` runtime spec version into its numeric
+    /// parts. Any pre-release (`-`) or build-metadata (`+`) suffix is stripped
+    /// first, so spec versions such as `1.0.2-dev` or `1.1.0-rc.2` parse to
+    /// their release triple per semver.
+    fn parse_version(version: &str) -> Result<(u32, u32, u32)> {
+        let core = version
+            .split_once(['-', '+'])
+            .map(|(core, _suffix)| core)
+            .unwrap_or(version);
+
+        let mut parts = core.split('.');
+        let mut next = |component: &str| -> Result<u32> {
+            parts
+                .next()
+                .with_context(|| format!("missing {} component", component))?
+                .parse::<u32>()
+                .with_context(|| format!("invalid {} component", component))
+        };
+
+        let major = next("major")?;
+        let minor = next("minor")?;
+        let patch = next("patch")?;
+
+        if parts.next().is_some() {
+            bail!("version has too many components");
+        }
+
+        Ok((major, minor, patch))
+    }
+
     fn create_container_state(&self, container_dir: &Path) -> Result<Container> {
         let container = Container::new(
             &self.base.container_id,
@@ -157,3 +429,96 @@ impl<'a> InitContainerBuilder<'a> {
         Ok(container)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_version_plain_triple() {
+        assert_eq!(
+            InitContainerBuilder::parse_version("1.0.2").unwrap(),
+            (1, 0, 2)
+        );
+    }
+
+    #[test]
+    fn parse_version_strips_pre_release_and_build_metadata() {
+        assert_eq!(
+            InitContainerBuilder::parse_version("1.0.2-dev").unwrap(),
+            (1, 0, 2)
+        );
+        assert_eq!(
+            InitContainerBuilder::parse_version("1.1.0-rc.2").unwrap(),
+            (1, 1, 0)
+        );
+        assert_eq!(
+            InitContainerBuilder::parse_version("1.0.0+build.5").unwrap(),
+            (1, 0, 0)
+        );
+    }
+
+    #[test]
+    fn parse_version_rejects_wrong_component_count() {
+        assert!(InitContainerBuilder::parse_version("1.0").is_err());
+        assert!(InitContainerBuilder::parse_version("1.0.0.0").is_err());
+    }
+
+    #[test]
+    fn validate_spec_version_accepts_supported_and_newer_minor() {
+        // Fully supported release and the 1.1.x bundles this unblocks.
+        InitContainerBuilder::validate_spec_version("1.0.2-dev").unwrap();
+        InitContainerBuilder::validate_spec_version("1.1.0-rc.2").unwrap();
+    }
+
+    #[test]
+    fn validate_spec_version_rejects_other_major() {
+        assert!(InitContainerBuilder::validate_spec_version("2.0.0").is_err());
+    }
+
+    fn hook(path: &str, timeout: Option<i32>) -> oci_spec::runtime::Hook {
+        let mut builder = oci_spec::runtime::HookBuilder::default().path(path);
+        if let Some(timeout) = timeout {
+            builder = builder.timeout(timeout);
+        }
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn validate_hook_rejects_non_positive_timeout() {
+        let hook = hook("/bin/sh", Some(0));
+        assert!(InitContainerBuilder::validate_hook("poststart", &hook, true).is_err());
+    }
+
+    #[test]
+    fn validate_hook_host_side_requires_existing_executable() {
+        let missing = hook("/no/such/hook/binary", Some(5));
+        assert!(InitContainerBuilder::validate_hook("prestart", &missing, true).is_err());
+
+        let present = hook("/bin/sh", Some(5));
+        InitContainerBuilder::validate_hook("prestart", &present, true).unwrap();
+    }
+
+    #[test]
+    fn validate_hook_in_container_stage_skips_host_path_check() {
+        // createContainer/startContainer run inside the container rootfs, so a
+        // path absent on the host must still validate.
+        let hook = hook("/no/such/hook/binary", Some(5));
+        InitContainerBuilder::validate_hook("createContainer", &hook, false).unwrap();
+    }
+
+    #[test]
+    fn validate_hooks_tolerates_prestart_with_lifecycle() {
+        let hooks = oci_spec::runtime::HooksBuilder::default()
+            .prestart(vec![hook("/bin/sh", None)])
+            .create_runtime(vec![hook("/bin/sh", None)])
+            .build()
+            .unwrap();
+        let spec = oci_spec::runtime::SpecBuilder::default()
+            .hooks(hooks)
+            .build()
+            .unwrap();
+        // Coexistence warns but must not fail validation.
+        InitContainerBuilder::validate_hooks(&spec).unwrap();
+    }
+}